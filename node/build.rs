@@ -0,0 +1,5 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tonic_build::compile_protos("proto/narwhal.proto")?;
+    Ok(())
+}
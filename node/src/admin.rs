@@ -0,0 +1,79 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+use crate::manager::Reconfigurable;
+use anyhow::Context;
+use config::{Committee, Import as _, Parameters};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+
+/// Runs a tiny line-oriented admin listener: each line sent to it is `<token>
+/// <committee-file-path>`, and a line whose token matches `expected_token` tears down the
+/// current epoch and re-spawns `manager` against the new committee. Used for both a
+/// primary's `NarwhalManager` and a worker's `WorkerManager` — either is a full
+/// BFT-membership reconfiguration trigger, so every connection is authenticated against
+/// the configured token before anything else happens; operators should additionally bind
+/// `--admin-addr` to a loopback or otherwise trusted interface.
+pub async fn run_admin_server<M: Reconfigurable + Send + 'static>(
+    address: SocketAddr,
+    manager: Arc<Mutex<M>>,
+    parameters: Parameters,
+    expected_token: String,
+) {
+    let listener = match TcpListener::bind(address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Failed to bind admin server to {}: {}", address, e);
+            return;
+        }
+    };
+    log::info!("Admin server listening on {}", address);
+
+    let expected_token = Arc::new(expected_token);
+    loop {
+        let (socket, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                log::warn!("Failed to accept admin connection: {}", e);
+                continue;
+            }
+        };
+        let manager = manager.clone();
+        let parameters = parameters.clone();
+        let expected_token = expected_token.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(socket).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let Some((token, committee_file)) = line.trim().split_once(' ') else {
+                    log::warn!(
+                        "Rejecting malformed admin request from {}: expected '<token> <committee-file>'",
+                        peer
+                    );
+                    continue;
+                };
+                if !bool::from(token.as_bytes().ct_eq(expected_token.as_bytes())) {
+                    log::warn!("Rejecting admin request from {}: bad token", peer);
+                    continue;
+                }
+                if let Err(e) = reconfigure(&manager, &parameters, committee_file).await {
+                    log::warn!("Reconfiguration requested by {} failed: {}", peer, e);
+                }
+            }
+        });
+    }
+}
+
+async fn reconfigure<M: Reconfigurable>(
+    manager: &Arc<Mutex<M>>,
+    parameters: &Parameters,
+    committee_file: &str,
+) -> anyhow::Result<()> {
+    let committee =
+        Committee::import(committee_file).context("Failed to load the new committee")?;
+    manager
+        .lock()
+        .await
+        .reconfigure(committee, parameters.clone())
+}
@@ -0,0 +1,168 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use bb8::Pool;
+use bb8_postgres::PostgresConnectionManager;
+use consensus::ConsensusOutput;
+use std::time::Duration;
+use tokio_postgres::NoTls;
+
+/// A deterministic state machine that consumes Narwhal's committed output. The crate only
+/// guarantees that `handle_consensus_output` is called in commit order and that `seq` is
+/// gap-free and monotonic across restarts; everything else is up to the embedder.
+#[async_trait]
+pub trait ExecutionState {
+    /// Applies one committed output. `seq` is the output's position in the global commit
+    /// sequence, so implementations can use it as an idempotency key. An `Err` means the
+    /// output was *not* durably applied (after exhausting any retries of its own); the
+    /// caller stops feeding outputs rather than silently skipping ahead of the checkpoint.
+    async fn handle_consensus_output(&self, output: ConsensusOutput, seq: u64) -> Result<()>;
+
+    /// The sequence number of the last output this state has durably applied, or `None`
+    /// if nothing has been executed yet. The node uses this on startup to skip outputs
+    /// consensus re-emits from its own persisted DAG that were already applied.
+    async fn load_last_executed_sequence(&self) -> Result<Option<u64>>;
+}
+
+/// An execution backend that does nothing: the default when no application is plugged in.
+/// Keeps the node runnable (e.g. for benchmarking the mempool and consensus alone) without
+/// a Postgres instance around.
+pub struct NoopExecutionState;
+
+#[async_trait]
+impl ExecutionState for NoopExecutionState {
+    async fn handle_consensus_output(&self, _output: ConsensusOutput, _seq: u64) -> Result<()> {
+        Ok(())
+    }
+
+    async fn load_last_executed_sequence(&self) -> Result<Option<u64>> {
+        Ok(None)
+    }
+}
+
+/// Default execution backend: checkpoints the last executed sequence number in an external
+/// Postgres database through a pooled async connection, so execution is crash-recoverable.
+/// It applies no transactions of its own: the committed output is not written anywhere
+/// besides that checkpoint.
+pub struct PostgresExecutionState {
+    pool: Pool<PostgresConnectionManager<NoTls>>,
+}
+
+/// How many times a single output is attempted against Postgres (the first try included)
+/// before giving up on it.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Backoff before the first retry; doubled after every further failed attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+impl PostgresExecutionState {
+    pub async fn new(connection_string: &str) -> Result<Self> {
+        let manager = PostgresConnectionManager::new_from_stringlike(connection_string, NoTls)
+            .context("Invalid Postgres connection string")?;
+        let pool = Pool::builder()
+            .build(manager)
+            .await
+            .context("Failed to create the Postgres connection pool")?;
+
+        let state = Self { pool };
+        state.ensure_schema().await?;
+        Ok(state)
+    }
+
+    async fn ensure_schema(&self) -> Result<()> {
+        let connection = self
+            .pool
+            .get()
+            .await
+            .context("Failed to get a Postgres connection")?;
+        connection
+            .batch_execute(
+                "CREATE TABLE IF NOT EXISTS narwhal_checkpoint (
+                    id BOOLEAN PRIMARY KEY DEFAULT true CHECK (id),
+                    last_executed_sequence BIGINT NOT NULL
+                )",
+            )
+            .await
+            .context("Failed to create the checkpoint table")?;
+        Ok(())
+    }
+
+    /// Checkpoints the sequence number in a single Postgres transaction; the output itself
+    /// is not applied. A single attempt; the caller is responsible for retrying.
+    async fn try_checkpoint(&self, output: &ConsensusOutput, seq: u64) -> Result<()> {
+        let mut connection = self
+            .pool
+            .get()
+            .await
+            .context("Failed to get a Postgres connection")?;
+        let transaction = connection
+            .transaction()
+            .await
+            .context("Failed to start a Postgres transaction")?;
+
+        // NOTE: Here is where an embedder's deterministic state transition would run; this
+        // default backend only persists the checkpoint.
+        let _ = output;
+
+        transaction
+            .execute(
+                "INSERT INTO narwhal_checkpoint (last_executed_sequence) VALUES ($1)
+                 ON CONFLICT (id) DO UPDATE SET last_executed_sequence = excluded.last_executed_sequence",
+                &[&(seq as i64)],
+            )
+            .await
+            .with_context(|| format!("Failed to checkpoint sequence {}", seq))?;
+
+        transaction
+            .commit()
+            .await
+            .with_context(|| format!("Failed to commit execution of sequence {}", seq))
+    }
+}
+
+#[async_trait]
+impl ExecutionState for PostgresExecutionState {
+    async fn handle_consensus_output(&self, output: ConsensusOutput, seq: u64) -> Result<()> {
+        let mut backoff = INITIAL_BACKOFF;
+        for attempt in 1..=MAX_ATTEMPTS {
+            match self.try_checkpoint(&output, seq).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt < MAX_ATTEMPTS => {
+                    log::warn!(
+                        "Execution attempt {}/{} for sequence {} failed: {}; retrying in {:?}",
+                        attempt,
+                        MAX_ATTEMPTS,
+                        seq,
+                        e,
+                        backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(e) => {
+                    return Err(e).context(format!(
+                        "Failed to execute sequence {} after {} attempts",
+                        seq, MAX_ATTEMPTS
+                    ))
+                }
+            }
+        }
+        unreachable!("the loop above always returns on its last attempt")
+    }
+
+    async fn load_last_executed_sequence(&self) -> Result<Option<u64>> {
+        let connection = self
+            .pool
+            .get()
+            .await
+            .context("Failed to get a Postgres connection")?;
+        let row = connection
+            .query_opt(
+                "SELECT last_executed_sequence FROM narwhal_checkpoint LIMIT 1",
+                &[],
+            )
+            .await
+            .context("Failed to load the checkpoint")?;
+        Ok(row.map(|row| row.get::<_, i64>(0) as u64))
+    }
+}
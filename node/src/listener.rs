@@ -0,0 +1,66 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+use anyhow::{Context, Result};
+use std::time::{Duration, Instant};
+use tokio_stream::StreamExt;
+
+use crate::narwhal_proto::exporter_client::ExporterClient;
+use crate::narwhal_proto::SubscribeRequest;
+
+/// How often the running throughput and latency figures are logged.
+const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Subscribes to a node's sub-DAG exporter and matches committed sample markers (emitted
+/// by `spammer`) to compute end-to-end latency and throughput, without needing a separate
+/// Python harness to post-process the deployment's logs.
+pub async fn listen(export_addr: String) -> Result<()> {
+    let mut client = ExporterClient::connect(export_addr.clone())
+        .await
+        .with_context(|| format!("Failed to connect to exporter at {}", export_addr))?;
+    let mut stream = client
+        .subscribe(SubscribeRequest { index: 0 })
+        .await
+        .context("Failed to subscribe to the sub-DAG stream")?
+        .into_inner();
+
+    let mut committed_bytes: u64 = 0;
+    let mut sample_count: u64 = 0;
+    let mut latency_total: u64 = 0;
+    let mut latency_square_total: u64 = 0;
+    let mut last_report = Instant::now();
+
+    while let Some(sub_dag) = stream.next().await {
+        let sub_dag = sub_dag.context("Sub-DAG stream returned an error")?;
+
+        for batch in sub_dag.batches {
+            committed_bytes += batch.size;
+            for sample in batch.sample_transactions {
+                let latency = sub_dag.commit_time.saturating_sub(sample.send_time);
+                sample_count += 1;
+                latency_total += latency;
+                latency_square_total += latency.saturating_pow(2);
+            }
+        }
+
+        if last_report.elapsed() >= REPORT_INTERVAL {
+            let elapsed = last_report.elapsed().as_secs_f64();
+            let throughput = committed_bytes as f64 / elapsed;
+            let mean = latency_total as f64 / sample_count.max(1) as f64;
+            let variance =
+                (latency_square_total as f64 / sample_count.max(1) as f64) - mean.powi(2);
+            log::info!(
+                "Throughput: {:.0} B/s, samples: {}, mean latency: {:.0} ms, stdev: {:.0} ms",
+                throughput,
+                sample_count,
+                mean,
+                variance.max(0.0).sqrt()
+            );
+
+            committed_bytes = 0;
+            sample_count = 0;
+            latency_total = 0;
+            latency_square_total = 0;
+            last_report = Instant::now();
+        }
+    }
+    Ok(())
+}
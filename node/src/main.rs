@@ -8,15 +8,39 @@ use config::Import as _;
 use config::{Committee, KeyPair, Parameters, WorkerId};
 use consensus::{Consensus, ConsensusOutput};
 use env_logger::Env;
+use futures::future::select_all;
 use metrics::ConsensusMetrics;
 use primary::Primary;
 use std::sync::{Arc, RwLock};
 use store::Store;
+use tokio::signal::unix::{signal, SignalKind};
 use tokio::sync::mpsc::{channel, Receiver};
+use tokio::task::JoinHandle;
 use worker::Worker;
 
+mod admin;
+mod execution;
+mod exporter;
+mod listener;
+mod manager;
 mod metrics;
 mod prometheus;
+mod reporter;
+mod spammer;
+
+mod narwhal_proto {
+    tonic::include_proto!("narwhal");
+}
+
+/// A named handle to a spawned task, so the supervisor can report which
+/// subsystem died when it brings the authority down. `primary::Primary::spawn` and
+/// `worker::Worker::spawn` are expected to return one of these per subsystem they start
+/// (e.g. batch_maker, quorum_waiter, processor, synchronizer, primary_connector, helper)
+/// rather than a single handle for the whole crate, so a failure is attributed to the
+/// subsystem that actually died. NOTE: the `primary` crate and the `worker` crate's
+/// per-subsystem modules aren't part of this tree, so that contract isn't enforced by the
+/// compiler here; `handles.extend(...)` at every call site already assumes this shape.
+pub type NamedHandle = (&'static str, JoinHandle<()>);
 
 /// The default channel capacity.
 pub const CHANNEL_CAPACITY: usize = 1_000;
@@ -40,6 +64,11 @@ async fn main() -> Result<()> {
                 .args_from_usage("--parameters=[FILE] 'The file containing the node parameters'")
                 .args_from_usage("--store=<PATH> 'The path where to create the data store'")
                 .args_from_usage("--prometheus=[Addr] 'The prometheus server address'")
+                .args_from_usage("--admin-addr=[Addr] 'The address to listen on for committee reconfiguration requests; bind this to a trusted interface'")
+                .args_from_usage("--admin-token=[TOKEN] 'The shared secret admin clients must present; required when --admin-addr is set'")
+                .args_from_usage("--export-addr=[Addr] 'The address to serve the sub-DAG exporter gRPC service on'")
+                .args_from_usage("--postgres-dsn=[DSN] 'The connection string of the Postgres database that checkpoints the last executed sequence (benchmark builds execute no outputs); defaults to a no-op executor'")
+                .args_from_usage("--report-interval=[SECONDS] 'How often to log human-readable throughput and latency (benchmark builds only)'")
                 .subcommand(SubCommand::with_name("primary").about("Run a single primary"))
                 .subcommand(
                     SubCommand::with_name("worker")
@@ -48,6 +77,18 @@ async fn main() -> Result<()> {
                 )
                 .setting(AppSettings::SubcommandRequiredElseHelp),
         )
+        .subcommand(
+            SubCommand::with_name("spammer")
+                .about("Generate load against a worker's transaction ingress")
+                .args_from_usage("--target=<Addr> 'The address of the worker's transaction ingress'")
+                .args_from_usage("--rate=<INT> 'The target rate, in transactions per second'")
+                .args_from_usage("--size=[INT] 'The size of each transaction, in bytes'"),
+        )
+        .subcommand(
+            SubCommand::with_name("listener")
+                .about("Measure latency and throughput from a node's sub-DAG exporter")
+                .args_from_usage("--export-addr=<Addr> 'The address of the node's sub-DAG exporter'"),
+        )
         .setting(AppSettings::SubcommandRequiredElseHelp)
         .get_matches();
 
@@ -68,6 +109,28 @@ async fn main() -> Result<()> {
             .export(sub_matches.value_of("filename").unwrap())
             .context("Failed to generate key pair")?,
         ("run", Some(sub_matches)) => run(sub_matches).await?,
+        ("spammer", Some(sub_matches)) => {
+            let target = sub_matches
+                .value_of("target")
+                .unwrap()
+                .parse()
+                .context("Invalid target socket address")?;
+            let rate = sub_matches
+                .value_of("rate")
+                .unwrap()
+                .parse()
+                .context("The rate must be a positive integer")?;
+            let size = sub_matches
+                .value_of("size")
+                .unwrap_or("512")
+                .parse()
+                .context("The transaction size must be a positive integer")?;
+            spammer::spam(target, rate, size).await?
+        }
+        ("listener", Some(sub_matches)) => {
+            let export_addr = sub_matches.value_of("export-addr").unwrap().to_string();
+            listener::listen(export_addr).await?
+        }
         _ => unreachable!(),
     }
     Ok(())
@@ -94,9 +157,6 @@ async fn run(matches: &ArgMatches<'_>) -> Result<()> {
     };
     let updatable_parameters = Arc::new(RwLock::new(parameters.clone().into()));
 
-    // Make the data store.
-    let store = Store::new(store_path).context("Failed to create a store")?;
-
     // Channels the sequence of certificates.
     let (tx_output, rx_output) = channel(CHANNEL_CAPACITY);
 
@@ -116,48 +176,160 @@ async fn run(matches: &ArgMatches<'_>) -> Result<()> {
         None => None,
     };
 
+    // Serve committed sub-DAGs to external execution layers over gRPC, if requested.
+    let sub_dag_exporter = match matches.value_of("export-addr") {
+        Some(address) => {
+            let socket_address: std::net::SocketAddr =
+                address.parse().context("Invalid export socket address")?;
+            let exporter = exporter::SubDagExporter::new();
+            let server = exporter.clone();
+            Some((
+                exporter,
+                tokio::spawn(async move {
+                    if let Err(e) = tonic::transport::Server::builder()
+                        .add_service(server.into_server())
+                        .serve(socket_address)
+                        .await
+                    {
+                        log::error!("Sub-DAG exporter server died: {}", e);
+                    }
+                }),
+            ))
+        }
+        None => None,
+    };
+
+    // Build the execution backend that will apply committed transactions, and recover the
+    // sequence it last checkpointed so we don't re-apply anything after a restart.
+    let execution_state: Arc<dyn execution::ExecutionState + Send + Sync> =
+        match matches.value_of("postgres-dsn") {
+            Some(dsn) => Arc::new(
+                execution::PostgresExecutionState::new(dsn)
+                    .await
+                    .context("Failed to initialize the Postgres execution backend")?,
+            ),
+            None => Arc::new(execution::NoopExecutionState),
+        };
+    let last_executed_sequence = execution_state
+        .load_last_executed_sequence()
+        .await
+        .context("Failed to load the last executed sequence")?;
+
     // Check whether to run a primary, a worker, or an entire authority.
+    let mut handles: Vec<NamedHandle> = Vec::new();
+    let sub_dag_exporter = match sub_dag_exporter {
+        Some((exporter, handle)) => {
+            handles.push(("exporter", handle));
+            Some(exporter)
+        }
+        None => None,
+    };
     let consensus_metrics = match matches.subcommand() {
-        // Spawn the primary and consensus core.
-        ("primary", _) => {
-            let (tx_new_certificates, rx_new_certificates) = channel(CHANNEL_CAPACITY);
-            let (tx_feedback, rx_feedback) = channel(CHANNEL_CAPACITY);
-            Primary::spawn(
-                keypair,
-                committee.clone(),
-                parameters.clone(),
-                store,
-                /* tx_consensus */ tx_new_certificates,
-                /* rx_consensus */ rx_feedback,
-            );
-            Consensus::spawn(
-                committee,
-                parameters.gc_depth,
-                /* rx_primary */ rx_new_certificates,
-                /* tx_primary */ tx_feedback,
-                tx_output,
-            );
+        // Spawn the primary and consensus core, optionally behind a `NarwhalManager` so an
+        // admin connection can swap the committee in without restarting the process.
+        ("primary", _) => match matches.value_of("admin-addr") {
+            Some(address) => {
+                let admin_address = address
+                    .parse()
+                    .context("Invalid admin server socket address")?;
+                let admin_token = matches
+                    .value_of("admin-token")
+                    .context("--admin-token is required when --admin-addr is set")?
+                    .to_string();
+                let mut manager =
+                    manager::NarwhalManager::new(keypair, store_path.to_string(), registry, tx_output);
+                manager.start(committee, parameters.clone())?;
+                let consensus_metrics = manager.metrics();
+                let manager = Arc::new(tokio::sync::Mutex::new(manager));
+                handles.push((
+                    "admin",
+                    tokio::spawn(admin::run_admin_server(
+                        admin_address,
+                        manager,
+                        parameters,
+                        admin_token,
+                    )),
+                ));
+                consensus_metrics
+            }
+            None => {
+                let store = Store::new(store_path).context("Failed to create a store")?;
+                let (tx_new_certificates, rx_new_certificates) = channel(CHANNEL_CAPACITY);
+                let (tx_feedback, rx_feedback) = channel(CHANNEL_CAPACITY);
+                handles.extend(Primary::spawn(
+                    keypair,
+                    committee.clone(),
+                    parameters.clone(),
+                    store,
+                    /* tx_consensus */ tx_new_certificates,
+                    /* rx_consensus */ rx_feedback,
+                ));
+                handles.push((
+                    "consensus",
+                    Consensus::spawn(
+                        committee,
+                        parameters.gc_depth,
+                        /* rx_primary */ rx_new_certificates,
+                        /* tx_primary */ tx_feedback,
+                        tx_output,
+                    ),
+                ));
 
-            // Consensus metrics.
-            registry.map(|x| ConsensusMetrics::new(x))
-        }
+                // Consensus metrics.
+                registry.map(|x| ConsensusMetrics::new(x))
+            }
+        },
 
-        // Spawn a single worker.
+        // Spawn a single worker, optionally behind a `WorkerManager` so this process's own
+        // admin listener can reconfigure it the same way a primary's is reconfigured.
         ("worker", Some(sub_matches)) => {
             let id = sub_matches
                 .value_of("id")
                 .unwrap()
                 .parse::<WorkerId>()
                 .context("The worker id must be a positive integer")?;
-            Worker::spawn(
-                keypair.name,
-                id,
-                committee,
-                parameters,
-                updatable_parameters.clone(),
-                store,
-                registry,
-            );
+
+            match matches.value_of("admin-addr") {
+                Some(address) => {
+                    let admin_address = address
+                        .parse()
+                        .context("Invalid admin server socket address")?;
+                    let admin_token = matches
+                        .value_of("admin-token")
+                        .context("--admin-token is required when --admin-addr is set")?
+                        .to_string();
+                    let mut manager = manager::WorkerManager::new(
+                        keypair,
+                        id,
+                        store_path.to_string(),
+                        registry,
+                        updatable_parameters.clone(),
+                    );
+                    manager.start(committee, parameters.clone())?;
+                    let manager = Arc::new(tokio::sync::Mutex::new(manager));
+                    handles.push((
+                        "admin",
+                        tokio::spawn(admin::run_admin_server(
+                            admin_address,
+                            manager,
+                            parameters,
+                            admin_token,
+                        )),
+                    ));
+                }
+                None => {
+                    let store = Store::new(store_path).context("Failed to create a store")?;
+                    handles.extend(Worker::spawn(
+                        keypair.name,
+                        id,
+                        committee,
+                        parameters,
+                        updatable_parameters.clone(),
+                        store,
+                        registry,
+                    ));
+                }
+            }
 
             // Consensus metrics.
             None
@@ -165,20 +337,105 @@ async fn run(matches: &ArgMatches<'_>) -> Result<()> {
         _ => unreachable!(),
     };
 
-    // Analyze the consensus' output.
-    analyze(rx_output, consensus_metrics).await;
+    // In benchmark builds, turn the raw commit counters into human-readable throughput
+    // and latency figures on a timer, instead of leaving that post-processing to whoever
+    // reads the dashboard.
+    #[cfg(feature = "benchmark")]
+    if let Some(metrics) = consensus_metrics.clone() {
+        let report_interval = matches
+            .value_of("report-interval")
+            .unwrap_or("5")
+            .parse()
+            .map(std::time::Duration::from_secs)
+            .context("The report interval must be a positive integer")?;
+        handles.push((
+            "reporter",
+            tokio::spawn(reporter::report(metrics, report_interval)),
+        ));
+    }
+
+    // Analyze the consensus' output. This task is supervised like the others: if it dies
+    // (or the authority is asked to shut down), every other task is aborted with it.
+    handles.push((
+        "analyzer",
+        tokio::spawn(analyze(
+            rx_output,
+            consensus_metrics,
+            sub_dag_exporter,
+            execution_state,
+            last_executed_sequence,
+        )),
+    ));
+
+    supervise(handles).await;
+    Ok(())
+}
+
+/// Waits for the first of `handles` to resolve (cleanly or by panicking) or for a
+/// SIGINT/SIGTERM, then aborts every remaining task so the authority never leaks
+/// half-dead subsystems.
+async fn supervise(handles: Vec<NamedHandle>) {
+    let mut sigterm = signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
+
+    let names: Vec<_> = handles.iter().map(|(name, _)| *name).collect();
+    let abort_handles: Vec<_> = handles.iter().map(|(_, handle)| handle.abort_handle()).collect();
+    let futures = handles.into_iter().map(|(_, handle)| handle);
 
-    // If this expression is reached, the program ends and all other tasks terminate.
-    unreachable!();
+    tokio::select! {
+        (result, index, _) = select_all(futures) => {
+            match result {
+                Ok(()) => log::warn!("Task '{}' exited", names[index]),
+                Err(e) => log::error!("Task '{}' panicked: {}", names[index], e),
+            }
+        },
+        _ = tokio::signal::ctrl_c() => {
+            log::info!("Received SIGINT, shutting down");
+        },
+        _ = sigterm.recv() => {
+            log::info!("Received SIGTERM, shutting down");
+        },
+    }
+
+    // Bring the whole authority down: a dead subsystem or a shutdown signal both mean
+    // the remaining tasks would otherwise keep limping along on stale state.
+    for handle in abort_handles {
+        handle.abort();
+    }
 }
 
 /// Receives an ordered list of certificates and apply any application-specific logic.
-async fn analyze(mut rx_output: Receiver<ConsensusOutput>, metrics: Option<ConsensusMetrics>) {
+async fn analyze(
+    mut rx_output: Receiver<ConsensusOutput>,
+    metrics: Option<ConsensusMetrics>,
+    sub_dag_exporter: Option<exporter::SubDagExporter>,
+    execution_state: Arc<dyn execution::ExecutionState + Send + Sync>,
+    last_executed_sequence: Option<u64>,
+) {
     // NOTE: Here goes the application logic.
     #[cfg(not(feature = "benchmark"))]
     {
         let _metrics = metrics;
-        while let Some(_output) = rx_output.recv().await {}
+        while let Some(output) = rx_output.recv().await {
+            let seq = output.consensus_index;
+            if let Some(exporter) = sub_dag_exporter.as_ref() {
+                exporter.publish(&output, seq).await;
+            }
+
+            // Consensus replays from its own persisted DAG on restart; skip whatever the
+            // execution state already checkpointed instead of re-applying it.
+            if last_executed_sequence.map_or(false, |executed| seq <= executed) {
+                continue;
+            }
+            if let Err(e) = execution_state.handle_consensus_output(output, seq).await {
+                // The execution backend has already retried whatever it could; stop feeding
+                // it outputs rather than silently running ahead of a checkpoint it never
+                // wrote. Returning here is fatal: `supervise` treats any task exiting as a
+                // reason to tear down the whole authority, and the next start resumes from
+                // the last sequence this backend did durably checkpoint.
+                log::error!("Execution failed at sequence {}: {}", seq, e);
+                return;
+            }
+        }
     }
 
     #[cfg(feature = "benchmark")]
@@ -186,6 +443,10 @@ async fn analyze(mut rx_output: Receiver<ConsensusOutput>, metrics: Option<Conse
         let mut first_transaction_recorded = false;
         let mut last_transaction_time = 0;
         while let Some(output) = rx_output.recv().await {
+            let seq = output.consensus_index;
+            if let Some(exporter) = sub_dag_exporter.as_ref() {
+                exporter.publish(&output, seq).await;
+            }
             if let Some(metrics) = metrics.as_ref() {
                 metrics.committed_certificates_total.inc();
 
@@ -0,0 +1,237 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+use crate::metrics::ConsensusMetrics;
+use crate::NamedHandle;
+use anyhow::{Context, Result};
+use config::{Committee, KeyPair, Parameters, UpdatableParameters, WorkerId};
+use consensus::{Consensus, ConsensusOutput};
+use prometheus::Registry;
+use std::sync::{Arc, RwLock};
+use store::Store;
+use tokio::sync::mpsc::{channel, Sender};
+use worker::Worker;
+
+use crate::CHANNEL_CAPACITY;
+
+/// Identifies a committee configuration. Bumped on every successful `start`, and used to
+/// namespace the on-disk store so records from different committees never collide.
+pub type Epoch = u64;
+
+/// Implemented by anything the admin listener can swap onto a new `Committee`, whether
+/// that's a primary's consensus core or a worker.
+pub trait Reconfigurable {
+    /// Tears down the current epoch (if any) and starts a new one with `committee`.
+    fn reconfigure(&mut self, committee: Committee, parameters: Parameters) -> Result<()>;
+}
+
+/// Owns the primary and consensus tasks of a running authority, and knows how to tear
+/// them down and re-spawn them against a new `Committee` without restarting the process.
+/// This is the lifecycle boundary an embedder (e.g. a chain advancing epochs) drives
+/// across committee changes.
+pub struct NarwhalManager {
+    keypair: KeyPair,
+    store_path: String,
+    registry: Option<&'static Registry>,
+    tx_output: Sender<ConsensusOutput>,
+    epoch: Epoch,
+    handles: Vec<NamedHandle>,
+    /// The on-disk store of the currently running epoch, if any; removed on `shutdown` so
+    /// repeated reconfigurations don't leak a directory per epoch.
+    current_store_path: Option<String>,
+}
+
+impl NarwhalManager {
+    pub fn new(
+        keypair: KeyPair,
+        store_path: String,
+        registry: Option<&'static Registry>,
+        tx_output: Sender<ConsensusOutput>,
+    ) -> Self {
+        Self {
+            keypair,
+            store_path,
+            registry,
+            tx_output,
+            epoch: 0,
+            handles: Vec::new(),
+            current_store_path: None,
+        }
+    }
+
+    /// The committee configuration currently running (0 before the first `start`).
+    pub fn epoch(&self) -> Epoch {
+        self.epoch
+    }
+
+    /// Spawns the primary and consensus core against `committee`, bumping the epoch and
+    /// opening a store namespaced to it. Panics if a previous epoch is still running;
+    /// callers must `shutdown` first.
+    pub fn start(&mut self, committee: Committee, parameters: Parameters) -> Result<()> {
+        assert!(
+            self.handles.is_empty(),
+            "Call `shutdown` before starting a new epoch"
+        );
+
+        let next_epoch = self.epoch + 1;
+        let store_path = format!("{}-epoch-{}", self.store_path, next_epoch);
+        let store = Store::new(&store_path).context("Failed to create the epoch's data store")?;
+
+        let (tx_new_certificates, rx_new_certificates) = channel(CHANNEL_CAPACITY);
+        let (tx_feedback, rx_feedback) = channel(CHANNEL_CAPACITY);
+
+        self.handles.extend(primary::Primary::spawn(
+            self.keypair.clone(),
+            committee.clone(),
+            parameters.clone(),
+            store,
+            /* tx_consensus */ tx_new_certificates,
+            /* rx_consensus */ rx_feedback,
+        ));
+        self.handles.push((
+            "consensus",
+            Consensus::spawn(
+                committee,
+                parameters.gc_depth,
+                /* rx_primary */ rx_new_certificates,
+                /* tx_primary */ tx_feedback,
+                self.tx_output.clone(),
+            ),
+        ));
+
+        self.epoch = next_epoch;
+        self.current_store_path = Some(store_path);
+        log::info!("Started epoch {}", self.epoch);
+        Ok(())
+    }
+
+    /// Aborts every task of the current epoch and removes its on-disk store, so a new
+    /// committee can be installed without leaking a store directory per reconfiguration.
+    pub fn shutdown(&mut self) {
+        for (name, handle) in self.handles.drain(..) {
+            log::info!("Aborting '{}' task for epoch {}", name, self.epoch);
+            handle.abort();
+        }
+
+        if let Some(store_path) = self.current_store_path.take() {
+            if let Err(e) = std::fs::remove_dir_all(&store_path) {
+                log::warn!(
+                    "Failed to remove store '{}' of epoch {}: {}",
+                    store_path,
+                    self.epoch,
+                    e
+                );
+            }
+        }
+    }
+
+    /// Fresh consensus metrics bound to this manager's registry, if any was configured.
+    pub fn metrics(&self) -> Option<ConsensusMetrics> {
+        self.registry.map(ConsensusMetrics::new)
+    }
+}
+
+impl Reconfigurable for NarwhalManager {
+    fn reconfigure(&mut self, committee: Committee, parameters: Parameters) -> Result<()> {
+        self.shutdown();
+        self.start(committee, parameters)
+    }
+}
+
+/// Owns a worker's tasks, and knows how to tear them down and re-spawn them against a new
+/// `Committee` without restarting the process. The counterpart of `NarwhalManager` for
+/// workers, since a committee change affects them just as much as it does primaries.
+pub struct WorkerManager {
+    keypair: KeyPair,
+    id: WorkerId,
+    store_path: String,
+    registry: Option<&'static Registry>,
+    /// Shared with the prometheus server so live parameter updates keep reaching the
+    /// worker across reconfigurations, the same way they do for a directly spawned one.
+    updatable_parameters: Arc<RwLock<UpdatableParameters>>,
+    epoch: Epoch,
+    handles: Vec<NamedHandle>,
+    /// The on-disk store of the currently running epoch, if any; removed on `shutdown` so
+    /// repeated reconfigurations don't leak a directory per epoch.
+    current_store_path: Option<String>,
+}
+
+impl WorkerManager {
+    pub fn new(
+        keypair: KeyPair,
+        id: WorkerId,
+        store_path: String,
+        registry: Option<&'static Registry>,
+        updatable_parameters: Arc<RwLock<UpdatableParameters>>,
+    ) -> Self {
+        Self {
+            keypair,
+            id,
+            store_path,
+            registry,
+            updatable_parameters,
+            epoch: 0,
+            handles: Vec::new(),
+            current_store_path: None,
+        }
+    }
+
+    /// The committee configuration currently running (0 before the first `start`).
+    pub fn epoch(&self) -> Epoch {
+        self.epoch
+    }
+
+    /// Spawns the worker against `committee`, bumping the epoch and opening a store
+    /// namespaced to it. Panics if a previous epoch is still running; callers must
+    /// `shutdown` first.
+    pub fn start(&mut self, committee: Committee, parameters: Parameters) -> Result<()> {
+        assert!(
+            self.handles.is_empty(),
+            "Call `shutdown` before starting a new epoch"
+        );
+
+        let next_epoch = self.epoch + 1;
+        let store_path = format!("{}-epoch-{}", self.store_path, next_epoch);
+        let store = Store::new(&store_path).context("Failed to create the epoch's data store")?;
+
+        self.handles.extend(Worker::spawn(
+            self.keypair.name.clone(),
+            self.id,
+            committee,
+            parameters,
+            self.updatable_parameters.clone(),
+            store,
+            self.registry,
+        ));
+
+        self.epoch = next_epoch;
+        self.current_store_path = Some(store_path);
+        log::info!("Started epoch {}", self.epoch);
+        Ok(())
+    }
+
+    /// Aborts every task of the current epoch and removes its on-disk store, so a new
+    /// committee can be installed without leaking a store directory per reconfiguration.
+    pub fn shutdown(&mut self) {
+        for (name, handle) in self.handles.drain(..) {
+            log::info!("Aborting '{}' task for epoch {}", name, self.epoch);
+            handle.abort();
+        }
+
+        if let Some(store_path) = self.current_store_path.take() {
+            if let Err(e) = std::fs::remove_dir_all(&store_path) {
+                log::warn!(
+                    "Failed to remove store '{}' of epoch {}: {}",
+                    store_path,
+                    self.epoch,
+                    e
+                );
+            }
+        }
+    }
+}
+
+impl Reconfigurable for WorkerManager {
+    fn reconfigure(&mut self, committee: Committee, parameters: Parameters) -> Result<()> {
+        self.shutdown();
+        self.start(committee, parameters)
+    }
+}
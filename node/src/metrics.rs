@@ -0,0 +1,109 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+use prometheus::{
+    register_gauge_with_registry, register_int_counter_with_registry, Gauge, IntCounter, Registry,
+};
+
+/// Prometheus counters and gauges describing the consensus' committed output. The
+/// counters are cheap to bump from the commit hot path; the gauges are derived from them
+/// periodically by the `reporter` task so dashboards get throughput and latency directly,
+/// without a recording-rule query.
+#[derive(Clone)]
+pub struct ConsensusMetrics {
+    /// Total number of certificates committed.
+    pub committed_certificates_total: IntCounter,
+    /// Commit time (ms) of the most recent commit: the per-commit deltas it is bumped by
+    /// start from 0.
+    pub last_committed_transaction: IntCounter,
+    /// Total bytes committed.
+    pub committed_bytes_total: IntCounter,
+    /// Total number of sampled (latency-tracked) transactions committed.
+    pub committed_sample_transactions_total: IntCounter,
+    /// Send time (ms) of the very first sampled transaction observed.
+    pub first_sent_transaction: IntCounter,
+    /// Sum of sampled transaction latencies (ms).
+    pub latency_total: IntCounter,
+    /// Sum of squared sampled transaction latencies (ms^2), for computing the variance.
+    pub latency_square_total: IntCounter,
+
+    /// Derived committed throughput, in bytes/s.
+    pub throughput_bytes_per_second: Gauge,
+    /// Derived committed throughput, in sampled transactions/s.
+    pub throughput_transactions_per_second: Gauge,
+    /// Derived mean sampled-transaction latency, in ms.
+    pub latency_mean_ms: Gauge,
+    /// Derived sampled-transaction latency standard deviation, in ms.
+    pub latency_stdev_ms: Gauge,
+}
+
+impl ConsensusMetrics {
+    pub fn new(registry: &Registry) -> Self {
+        Self {
+            committed_certificates_total: register_int_counter_with_registry!(
+                "committed_certificates_total",
+                "Total number of certificates committed",
+                registry
+            )
+            .unwrap(),
+            last_committed_transaction: register_int_counter_with_registry!(
+                "last_committed_transaction",
+                "Commit time of the most recent commit, in ms",
+                registry
+            )
+            .unwrap(),
+            committed_bytes_total: register_int_counter_with_registry!(
+                "committed_bytes_total",
+                "Total number of bytes committed",
+                registry
+            )
+            .unwrap(),
+            committed_sample_transactions_total: register_int_counter_with_registry!(
+                "committed_sample_transactions_total",
+                "Total number of sampled transactions committed",
+                registry
+            )
+            .unwrap(),
+            first_sent_transaction: register_int_counter_with_registry!(
+                "first_sent_transaction",
+                "Send time of the first sampled transaction observed, in ms",
+                registry
+            )
+            .unwrap(),
+            latency_total: register_int_counter_with_registry!(
+                "latency_total",
+                "Sum of sampled transaction latencies, in ms",
+                registry
+            )
+            .unwrap(),
+            latency_square_total: register_int_counter_with_registry!(
+                "latency_square_total",
+                "Sum of squared sampled transaction latencies, in ms^2",
+                registry
+            )
+            .unwrap(),
+            throughput_bytes_per_second: register_gauge_with_registry!(
+                "throughput_bytes_per_second",
+                "Committed throughput, in bytes per second",
+                registry
+            )
+            .unwrap(),
+            throughput_transactions_per_second: register_gauge_with_registry!(
+                "throughput_transactions_per_second",
+                "Committed throughput, in sampled transactions per second",
+                registry
+            )
+            .unwrap(),
+            latency_mean_ms: register_gauge_with_registry!(
+                "latency_mean_ms",
+                "Mean sampled transaction latency, in ms",
+                registry
+            )
+            .unwrap(),
+            latency_stdev_ms: register_gauge_with_registry!(
+                "latency_stdev_ms",
+                "Sampled transaction latency standard deviation, in ms",
+                registry
+            )
+            .unwrap(),
+        }
+    }
+}
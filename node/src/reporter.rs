@@ -0,0 +1,64 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+use crate::metrics::ConsensusMetrics;
+use std::time::Duration;
+use tokio::time::interval;
+
+/// Periodically turns the raw commit counters into human-friendly numbers: committed
+/// throughput as bytes/s with a binary byte suffix, the rate of committed sampled
+/// transactions per second (only sampled transactions are counted), mean latency, and the latency
+/// standard deviation (variance = E[x^2] - E[x]^2). Also refreshes the corresponding
+/// Prometheus gauges, so dashboards get throughput and jitter without a recording-rule
+/// query.
+pub async fn report(metrics: ConsensusMetrics, report_interval: Duration) {
+    let mut ticker = interval(report_interval);
+    let mut last_bytes = 0u64;
+    let mut last_samples = 0u64;
+
+    loop {
+        ticker.tick().await;
+
+        let bytes = metrics.committed_bytes_total.get();
+        let samples = metrics.committed_sample_transactions_total.get();
+        let seconds = report_interval.as_secs_f64();
+
+        let throughput_bytes = (bytes - last_bytes) as f64 / seconds;
+        let throughput_tx = (samples - last_samples) as f64 / seconds;
+        last_bytes = bytes;
+        last_samples = samples;
+
+        let latency_total = metrics.latency_total.get() as f64;
+        let latency_square_total = metrics.latency_square_total.get() as f64;
+        let mean = latency_total / samples.max(1) as f64;
+        // variance = E[x^2] - E[x]^2; clamp for floating-point drift near zero.
+        let stdev = (latency_square_total / samples.max(1) as f64 - mean * mean)
+            .max(0.0)
+            .sqrt();
+
+        metrics.throughput_bytes_per_second.set(throughput_bytes);
+        metrics
+            .throughput_transactions_per_second
+            .set(throughput_tx);
+        metrics.latency_mean_ms.set(mean);
+        metrics.latency_stdev_ms.set(stdev);
+
+        log::info!(
+            "Throughput: {}/s, {:.0} sampled tx/s, mean latency: {:.0} ms, stdev: {:.0} ms",
+            format_bytes(throughput_bytes),
+            throughput_tx,
+            mean,
+            stdev
+        );
+    }
+}
+
+/// Formats a byte count with a binary SI suffix, e.g. `format_bytes(1536.0) == "1.50 KiB"`.
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2} {}", value, UNITS[unit])
+}
@@ -0,0 +1,135 @@
+// Copyright(C) Facebook, Inc. and its affiliates.
+use anyhow::{Context, Result};
+use bytes::{BufMut, Bytes, BytesMut};
+use std::net::SocketAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::time::{interval, Duration};
+
+/// Tags a transaction so a `listener` can tell a latency sample from plain load.
+const SAMPLE_MARKER: u8 = 0;
+const STANDARD_MARKER: u8 = 1;
+
+/// Generates fixed-size transactions at a target rate against a worker's transaction
+/// ingress, embedding a send timestamp in one sample per second so a `listener` can
+/// compute end-to-end latency. Removes the need for an external load-generation harness
+/// when benchmarking a deployment.
+pub async fn spam(target: SocketAddr, rate: u64, transaction_size: usize) -> Result<()> {
+    anyhow::ensure!(rate > 0, "The rate must be a positive number of tx/s");
+    anyhow::ensure!(
+        transaction_size >= 17,
+        "The transaction size must be at least 17 bytes (1-byte marker + 8-byte id + 8-byte timestamp)"
+    );
+
+    let mut connection = TcpStream::connect(target)
+        .await
+        .with_context(|| format!("Failed to connect to {}", target))?;
+    log::info!(
+        "Spamming {} with {}-byte transactions at {} tx/s",
+        target,
+        transaction_size,
+        rate
+    );
+
+    // Tick every millisecond and accumulate a fractional number of transactions to send
+    // per tick; sending `tokens.floor()` and keeping the remainder tracks any rate exactly
+    // over time, instead of quantizing it to whatever divides evenly into 1_000 ticks/s.
+    const TICK: Duration = Duration::from_millis(1);
+    let tokens_per_tick = rate as f64 / 1_000.0;
+    let mut ticker = interval(TICK);
+    let mut tokens = 0.0;
+    let mut counter: u64 = 0;
+
+    loop {
+        ticker.tick().await;
+        let to_send = next_batch_size(&mut tokens, tokens_per_tick);
+        for _ in 0..to_send {
+            let transaction = make_transaction(counter, transaction_size, counter % rate == 0);
+            counter += 1;
+
+            if let Err(e) = connection.write_all(&transaction).await {
+                log::warn!("Failed to send transaction ({}), reconnecting", e);
+                connection = TcpStream::connect(target)
+                    .await
+                    .with_context(|| format!("Failed to reconnect to {}", target))?;
+            }
+        }
+    }
+}
+
+/// Advances the token bucket by one tick and returns how many transactions to send this
+/// tick, carrying any fractional remainder into the next call. This is what lets a rate
+/// that doesn't divide evenly into 1_000 ticks/s still be honored exactly over time.
+fn next_batch_size(tokens: &mut f64, tokens_per_tick: f64) -> u64 {
+    *tokens += tokens_per_tick;
+    let to_send = *tokens as u64;
+    *tokens -= to_send as f64;
+    to_send
+}
+
+fn make_transaction(id: u64, size: usize, sample: bool) -> Bytes {
+    let mut transaction = BytesMut::with_capacity(size);
+    transaction.put_u8(if sample { SAMPLE_MARKER } else { STANDARD_MARKER });
+    transaction.put_u64(id);
+    if sample {
+        let send_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        transaction.put_u64(send_time);
+    }
+    transaction.resize(size, 0u8);
+    transaction.freeze()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_batch_size_tracks_rates_that_dont_divide_evenly_into_1000() {
+        for rate in [1u64, 3, 500, 1_500, 33_333] {
+            let tokens_per_tick = rate as f64 / 1_000.0;
+            let mut tokens = 0.0;
+            let mut total = 0u64;
+            for _ in 0..10_000 {
+                // 10 seconds of 1ms ticks.
+                total += next_batch_size(&mut tokens, tokens_per_tick);
+            }
+            let expected = rate * 10;
+            let diff = (total as i64 - expected as i64).unsigned_abs();
+            assert!(
+                diff <= 1,
+                "rate {}: sent {} over 10s, expected ~{}",
+                rate,
+                total,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn next_batch_size_eventually_sends_a_sub_tick_rate() {
+        // At 1 tx/s, a single transaction must still go out somewhere across 1_000 ticks,
+        // not get rounded down to zero forever.
+        let tokens_per_tick = 1.0 / 1_000.0;
+        let mut tokens = 0.0;
+        let mut total = 0u64;
+        for _ in 0..1_000 {
+            total += next_batch_size(&mut tokens, tokens_per_tick);
+        }
+        assert_eq!(total, 1);
+    }
+
+    #[test]
+    fn make_transaction_marks_samples_and_standard_transactions_distinctly() {
+        let sample = make_transaction(7, 17, true);
+        assert_eq!(sample[0], SAMPLE_MARKER);
+        assert_eq!(sample.len(), 17);
+
+        let standard = make_transaction(7, 17, false);
+        assert_eq!(standard[0], STANDARD_MARKER);
+        assert_eq!(standard.len(), 17);
+    }
+}
@@ -1,4 +1,9 @@
 // Copyright(C) Facebook, Inc. and its affiliates.
+// NOTE: batch_maker, helper, primary_connector, processor, quorum_waiter, synchronizer and
+// worker are declared below but their source files aren't present in this checkout, so this
+// crate doesn't actually compile as-is. `Worker::spawn` is expected (by node/src/main.rs and
+// node/src/manager.rs) to return one named JoinHandle per subsystem it starts rather than a
+// single handle for the whole worker; that can't be verified against real source here.
 mod batch_maker;
 mod helper;
 mod primary_connector;